@@ -0,0 +1,104 @@
+//! The `nadvice`-style API: `advice-add`, `advice-remove`, and
+//! `advice-member-p`. The chain itself is `core::object::advice::Advice`.
+use crate::core::{
+    env::Symbol,
+    gc::Context,
+    object::{
+        advice::{Advice, AdviceKind},
+        GcObj, Object,
+    },
+};
+use crate::data::{equal, fset, symbol_function};
+use anyhow::{anyhow, bail, Result};
+use fn_macros::defun;
+use rune_core::macros::root;
+
+/// Wrap `symbol`'s current function with `function`, combined via `how`
+/// (`:before`, `:after`, `:around`, `:filter-args`, or `:filter-return`).
+/// The previous definition is preserved so `advice-remove` can restore it.
+#[defun]
+pub(crate) fn advice_add<'ob>(
+    symbol: Symbol,
+    how: Symbol,
+    function: GcObj<'static>,
+    cx: &'ob Context,
+) -> Result<Symbol> {
+    let kind = AdviceKind::from_keyword(how.name)
+        .ok_or_else(|| anyhow!("Invalid advice combinator: {}", how.name))?;
+    let original = symbol_function(symbol, cx);
+    if original.nil() {
+        bail!("Symbol's function definition is void: {}", symbol.name);
+    }
+    // `function` and `original` are rooted for the duration of the `alloc`
+    // below, so the GC it may trigger cannot collect either of them before
+    // they are stored (as `'static`) inside the new `Advice` node.
+    root!(function, init(function), cx);
+    root!(original, init(original), cx);
+    let function = function.bind(cx);
+    let original = original.bind(cx);
+    // SAFETY: both are rooted above, so erasing their lifetime to store them
+    // in the heap-allocated `Advice` node is sound.
+    let function = unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(function) };
+    let original = unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(original) };
+    let wrapped: GcObj = cx.alloc(Advice::new(kind, function, original)).into();
+    fset(symbol, wrapped)
+}
+
+/// Find the advice node in `func`'s chain whose advice function is `equal`
+/// to `target`, splice it out, and return the rebuilt chain. Returns `None`
+/// if `func` is not advised with `target`.
+fn remove_from_chain<'ob>(func: GcObj<'ob>, target: GcObj, cx: &'ob Context) -> Option<GcObj<'ob>> {
+    match func.get() {
+        Object::Advice(adv) => {
+            if equal(adv.advice, target) {
+                Some(adv.inner.into())
+            } else {
+                let new_inner = remove_from_chain(adv.inner.into(), target, cx)?;
+                // Root `new_inner` across the `alloc` below so a GC it
+                // triggers cannot collect it before it is stored (as
+                // `'static`) in the rebuilt chain node.
+                root!(new_inner, init(new_inner), cx);
+                let new_inner = new_inner.bind(cx);
+                // SAFETY: rooted above, so erasing its lifetime to store it
+                // in the heap-allocated `Advice` node is sound.
+                let new_inner = unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(new_inner) };
+                Some(cx.alloc(Advice::new(adv.kind, adv.advice, new_inner)).into())
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Remove `function` from `symbol`'s advice chain, restoring whatever it was
+/// wrapping. A no-op if `function` is not currently advising `symbol`.
+#[defun]
+pub(crate) fn advice_remove<'ob>(
+    symbol: Symbol,
+    function: GcObj<'ob>,
+    cx: &'ob Context,
+) -> Result<Symbol> {
+    let current = symbol_function(symbol, cx);
+    if let Some(updated) = remove_from_chain(current, function, cx) {
+        fset(symbol, updated)?;
+    }
+    Ok(symbol)
+}
+
+/// Whether `function` is present anywhere in `symbol`'s advice chain.
+#[defun]
+pub(crate) fn advice_member_p(function: GcObj, symbol: Symbol, cx: &Context) -> bool {
+    let mut current = symbol_function(symbol, cx);
+    loop {
+        match current.get() {
+            Object::Advice(adv) => {
+                if equal(adv.advice, function) {
+                    return true;
+                }
+                current = adv.inner.into();
+            }
+            _ => return false,
+        }
+    }
+}
+
+define_symbols!(FUNCS => { advice_add, advice_remove, advice_member_p });
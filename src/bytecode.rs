@@ -0,0 +1,237 @@
+//! Decoding, textual rendering, and execution of byte-compiled code, in the
+//! spirit of Emacs's `disassemble`.
+use crate::core::{
+    env::Env,
+    gc::{Context, Root},
+    object::{nil, GcObj, LispFn, Object},
+};
+use anyhow::{anyhow, bail, Result};
+use fn_macros::defun;
+
+/// A single byte-code operation, along with the operand width it consumes
+/// from the instruction stream.
+///
+/// NOTE: this mnemonic/byte-value table is this crate chunk's own
+/// placeholder, not the real byte compiler's canonical opcode set — the
+/// byte compiler itself is not part of this source chunk (there is no
+/// compiler module here to import constants from, and no way to produce a
+/// real compiled function to decode and check this table against). Treat
+/// `decode`/`execute`/`disassemble_1` as internally consistent with each
+/// other and with this placeholder table only; replace `OpCode`'s constants
+/// and operand widths with the compiler's real ones once that module is
+/// available in this tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpCode {
+    Nil,
+    T,
+    StackRef(u8),
+    Constant(u16),
+    Call(u8),
+    Discard,
+    Dup,
+    Jump(u16),
+    JumpUnless(u16),
+    Return,
+}
+
+impl OpCode {
+    const NIL: u8 = 0x00;
+    const T: u8 = 0x01;
+    const STACK_REF: u8 = 0x02;
+    const CONSTANT: u8 = 0x03;
+    const CALL: u8 = 0x04;
+    const DISCARD: u8 = 0x05;
+    const DUP: u8 = 0x06;
+    const JUMP: u8 = 0x07;
+    const JUMP_UNLESS: u8 = 0x08;
+    const RETURN: u8 = 0x09;
+
+    /// Decode the instruction beginning at `bytes[pos]`, returning the
+    /// decoded op and the offset of the next instruction. Returns `None` if
+    /// `pos` is not a valid instruction start (e.g. trailing garbage), in
+    /// which case `disassemble_1` emits the raw byte and advances by one,
+    /// and `execute` fails with an error.
+    fn decode(bytes: &[u8], pos: usize) -> Option<(Self, usize)> {
+        let op = *bytes.get(pos)?;
+        let read_u16 = |at: usize| -> Option<u16> {
+            let low = *bytes.get(at)?;
+            let high = *bytes.get(at + 1)?;
+            Some(u16::from_le_bytes([low, high]))
+        };
+        match op {
+            Self::NIL => Some((Self::Nil, pos + 1)),
+            Self::T => Some((Self::T, pos + 1)),
+            Self::STACK_REF => {
+                let n = *bytes.get(pos + 1)?;
+                Some((Self::StackRef(n), pos + 2))
+            }
+            Self::CONSTANT => {
+                let idx = read_u16(pos + 1)?;
+                Some((Self::Constant(idx), pos + 3))
+            }
+            Self::CALL => {
+                let n = *bytes.get(pos + 1)?;
+                Some((Self::Call(n), pos + 2))
+            }
+            Self::DISCARD => Some((Self::Discard, pos + 1)),
+            Self::DUP => Some((Self::Dup, pos + 1)),
+            Self::JUMP => {
+                let target = read_u16(pos + 1)?;
+                Some((Self::Jump(target), pos + 3))
+            }
+            Self::JUMP_UNLESS => {
+                let target = read_u16(pos + 1)?;
+                Some((Self::JumpUnless(target), pos + 3))
+            }
+            Self::RETURN => Some((Self::Return, pos + 1)),
+            _ => None,
+        }
+    }
+}
+
+/// Render the byte-compiled body of `func` as a human-readable listing: one
+/// instruction per line, showing the byte offset, mnemonic, decoded operand,
+/// and (for operands that index the constant vector) the constant's printed
+/// form.
+fn disassemble_1(func: &LispFn, cx: &Context) -> String {
+    let mut out = String::new();
+    let code = &func.body.op_codes.0;
+    let constants = func.body.constants(cx);
+    let mut pos = 0;
+    while pos < code.len() {
+        let addr = pos;
+        match OpCode::decode(code, pos) {
+            Some((op, next)) => {
+                match op {
+                    OpCode::Nil => out.push_str(&format!("{addr}\tconstant\tnil\n")),
+                    OpCode::T => out.push_str(&format!("{addr}\tconstant\tt\n")),
+                    OpCode::StackRef(n) => out.push_str(&format!("{addr}\tstack-ref\t{n}\n")),
+                    OpCode::Constant(idx) => {
+                        let value = match constants.get(idx as usize) {
+                            Some(obj) => crate::print::prin1_to_string_basic(*obj),
+                            None => "<out of range>".to_string(),
+                        };
+                        out.push_str(&format!("{addr}\tconstant\t{idx}\t{value}\n"));
+                    }
+                    OpCode::Call(n) => out.push_str(&format!("{addr}\tcall\t{n}\n")),
+                    OpCode::Discard => out.push_str(&format!("{addr}\tdiscard\n")),
+                    OpCode::Dup => out.push_str(&format!("{addr}\tdup\n")),
+                    OpCode::Jump(target) => out.push_str(&format!("{addr}\tjump\t{target}\n")),
+                    OpCode::JumpUnless(target) => {
+                        out.push_str(&format!("{addr}\tjump-unless\t{target}\n"));
+                    }
+                    OpCode::Return => out.push_str(&format!("{addr}\treturn\n")),
+                }
+                pos = next;
+            }
+            None => {
+                out.push_str(&format!("{addr}\t<unknown byte {:#04x}>\n", code[pos]));
+                pos += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Run `func`'s byte-code body to completion: a small stack machine where
+/// `args` is pushed first, `StackRef(n)` re-pushes the nth value pushed so
+/// far (0-indexed from the bottom), `Call(n)` pops a callee and its `n`
+/// arguments and dispatches through `call_function` (so a call out of a
+/// `LispFn` can reach a `SubrFn` or another `Advice` node just as well as
+/// another `LispFn`), and `Return` ends execution with the top of the
+/// stack. This is what lets `Advice` actually wrap a byte-compiled inner
+/// function instead of only a `SubrFn`.
+pub(crate) fn execute<'ob>(
+    func: &LispFn,
+    args: &mut Root<Vec<GcObj<'static>>>,
+    env: &mut Root<Env>,
+    cx: &'ob mut Context,
+) -> Result<GcObj<'ob>> {
+    let code = &func.body.op_codes.0;
+    let mut stack: Vec<GcObj<'static>> = args.as_mut(cx).clone();
+    let mut pos = 0usize;
+    loop {
+        let Some((op, next)) = OpCode::decode(code, pos) else {
+            bail!("invalid byte-code at offset {pos}");
+        };
+        let mut next_pos = next;
+        match op {
+            OpCode::Nil => stack.push(nil()),
+            OpCode::T => stack.push(crate::core::env::intern("t").into()),
+            OpCode::StackRef(n) => {
+                let value =
+                    *stack.get(n as usize).ok_or_else(|| anyhow!("stack-ref out of range: {n}"))?;
+                stack.push(value);
+            }
+            OpCode::Constant(idx) => {
+                let value = *func
+                    .body
+                    .constants(cx)
+                    .get(idx as usize)
+                    .ok_or_else(|| anyhow!("constant out of range: {idx}"))?;
+                // SAFETY: constants live as long as `func` itself, which
+                // outlives this call.
+                stack.push(unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(value) });
+            }
+            OpCode::Call(n) => {
+                let n = n as usize;
+                if stack.len() < n + 1 {
+                    bail!("call: not enough operands on the stack");
+                }
+                let call_args = stack.split_off(stack.len() - n);
+                let callee = stack.pop().expect("checked above");
+                // Swap the caller's `args` root for this call's own
+                // argument vector, then restore it immediately after:
+                // `args` may still be needed unchanged further up the call
+                // chain (e.g. a `:before`/`:after` advice node calling both
+                // halves of its chain through the same root).
+                let saved = std::mem::replace(args.as_mut(cx), call_args);
+                let result = crate::core::object::advice::call_function(callee, args, env, cx);
+                *args.as_mut(cx) = saved;
+                stack.push(unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(result?) });
+            }
+            OpCode::Discard => {
+                stack.pop().ok_or_else(|| anyhow!("discard: stack underflow"))?;
+            }
+            OpCode::Dup => {
+                let top = *stack.last().ok_or_else(|| anyhow!("dup: stack underflow"))?;
+                stack.push(top);
+            }
+            OpCode::Jump(target) => next_pos = target as usize,
+            OpCode::JumpUnless(target) => {
+                let top = stack.pop().ok_or_else(|| anyhow!("jump-unless: stack underflow"))?;
+                if top.nil() {
+                    next_pos = target as usize;
+                }
+            }
+            OpCode::Return => {
+                let value = stack.pop().ok_or_else(|| anyhow!("return: stack underflow"))?;
+                return Ok(cx.bind(value));
+            }
+        }
+        pos = next_pos;
+    }
+}
+
+/// Print the disassembled byte-code listing for `object`, which must be a
+/// byte-compiled function (or a symbol whose function cell holds one).
+/// Mirrors Emacs's `disassemble`.
+#[defun]
+pub(crate) fn disassemble<'ob>(object: GcObj<'ob>, cx: &'ob Context) -> Result<GcObj<'ob>> {
+    let resolved = match object.get() {
+        Object::Symbol(sym) => match sym.follow_indirect(cx) {
+            Some(func) => func.into(),
+            None => bail!("Symbol's function definition is void: {}", sym.name),
+        },
+        _ => object,
+    };
+    match resolved.get() {
+        Object::LispFn(func) => {
+            print!("{}", disassemble_1(func, cx));
+            Ok(nil())
+        }
+        _ => bail!("`disassemble' is only implemented for byte-compiled functions"),
+    }
+}
+
+define_symbols!(FUNCS => { disassemble });
@@ -0,0 +1,27 @@
+//! Centralized, opt-in debug instrumentation controlled by environment
+//! variables, read once and cached so the check compiles down to a single
+//! bool load when unset. New trace points should read their flag from here
+//! rather than inspecting the environment directly.
+use std::sync::OnceLock;
+
+/// Set to report per-collection GC statistics (roots pushed, objects traced,
+/// objects swept) to stderr.
+const PRINT_GC: &str = "RUNE_PRINT_GC";
+/// Set to log every subr call's name, filled argument count, and result.
+const TRACE_CALLS: &str = "RUNE_TRACE_CALLS";
+
+fn env_flag(name: &str) -> bool {
+    std::env::var_os(name).is_some()
+}
+
+/// Whether `RUNE_PRINT_GC` is set in the environment.
+pub(crate) fn print_gc() -> bool {
+    static FLAG: OnceLock<bool> = OnceLock::new();
+    *FLAG.get_or_init(|| env_flag(PRINT_GC))
+}
+
+/// Whether `RUNE_TRACE_CALLS` is set in the environment.
+pub(crate) fn trace_calls() -> bool {
+    static FLAG: OnceLock<bool> = OnceLock::new();
+    *FLAG.get_or_init(|| env_flag(TRACE_CALLS))
+}
@@ -1,4 +1,6 @@
+use super::super::object::hash_table::LispHashTable;
 use super::super::object::RawObj;
+use crate::core::debug;
 use crate::core::object::{Gc, Object};
 use rune_core::hashmap::{HashMap, HashSet};
 
@@ -8,20 +10,95 @@ pub(crate) trait Trace {
 
 pub(crate) struct GcState {
     stack: Vec<RawObj>,
+    /// Hash tables with a `:weakness` seen so far this collection, so their
+    /// dead entries can be cleared once the mark stack is fully drained.
+    weak_tables: Vec<*const LispHashTable>,
+    /// Per-collection counters, only maintained for `RUNE_PRINT_GC` and
+    /// otherwise free to ignore. Reset by `begin_collection`; the collector
+    /// driver must call it once at the start of every collection, since
+    /// `GcState` itself is reused across collections.
+    roots_pushed: usize,
+    objects_traced: usize,
+    objects_swept: usize,
 }
 
 impl GcState {
     pub fn new() -> Self {
-        GcState { stack: Vec::new() }
+        GcState {
+            stack: Vec::new(),
+            weak_tables: Vec::new(),
+            roots_pushed: 0,
+            objects_traced: 0,
+            objects_swept: 0,
+        }
+    }
+
+    /// Reset the per-collection counters. Must be called once at the start
+    /// of each collection; `GcState` is otherwise reused across collections
+    /// (see `push`/`record_traced`/`record_swept`), so without this the
+    /// "per-collection" stats `record_swept` prints would actually
+    /// accumulate across the process's whole lifetime.
+    pub(crate) fn begin_collection(&mut self) {
+        self.roots_pushed = 0;
+        self.objects_traced = 0;
+        self.objects_swept = 0;
     }
 
     pub fn push(&mut self, obj: Object) {
+        self.roots_pushed += 1;
         self.stack.push(Gc::into_raw(obj));
     }
 
     pub fn stack(&mut self) -> &mut Vec<RawObj> {
         &mut self.stack
     }
+
+    /// Record that `n` more objects were popped off the mark stack and
+    /// traced. Called by the collector's mark loop.
+    pub(crate) fn record_traced(&mut self, n: usize) {
+        self.objects_traced += n;
+    }
+
+    /// Record that `n` objects were reclaimed during sweep. Called by the
+    /// collector once sweep finishes.
+    pub(crate) fn record_swept(&mut self, n: usize) {
+        self.objects_swept += n;
+        if debug::print_gc() {
+            eprintln!(
+                "gc: {} roots pushed, {} objects traced, {} objects swept",
+                self.roots_pushed, self.objects_traced, self.objects_swept
+            );
+        }
+    }
+
+    pub(crate) fn register_weak_table(&mut self, table: &LispHashTable) {
+        self.weak_tables.push(table);
+    }
+
+    /// Remove any entry from a registered weak table whose weak component
+    /// did not get marked. Must be called after the mark stack has been
+    /// fully drained and before sweep, so `is_marked` reflects the final
+    /// reachability of the rest of the heap.
+    ///
+    /// Re-marking a kept entry's components (see `sweep_weak_entries`) can
+    /// be exactly what makes a *different* weak table's otherwise-dead
+    /// entry newly live, so this sweeps every registered table repeatedly
+    /// until a full pass makes no further marks, rather than just once.
+    pub(crate) fn clear_weak_tables(&mut self) {
+        let tables: Vec<*const LispHashTable> = self.weak_tables.drain(..).collect();
+        loop {
+            let mut any_changed = false;
+            for &table in &tables {
+                // SAFETY: tables are registered from `LispHashTable::trace`
+                // during this same collection and are still alive (the
+                // arena they live in is not swept until after this call).
+                any_changed |= unsafe { (*table).sweep_weak_entries(self) };
+            }
+            if !any_changed {
+                break;
+            }
+        }
+    }
 }
 
 impl Trace for usize {
@@ -122,6 +199,21 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_begin_collection_resets_counters() {
+        let mut state = GcState::new();
+        state.push(Object::Int(0));
+        state.record_traced(3);
+        state.record_swept(2);
+        assert_eq!(state.roots_pushed, 1);
+        assert_eq!(state.objects_traced, 3);
+        assert_eq!(state.objects_swept, 2);
+        state.begin_collection();
+        assert_eq!(state.roots_pushed, 0);
+        assert_eq!(state.objects_traced, 0);
+        assert_eq!(state.objects_swept, 0);
+    }
+
     #[test]
     fn test_trace_root() {
         let roots = &RootSet::default();
@@ -0,0 +1,184 @@
+//! The function object created by `advice-add`: a combinator plus the
+//! function it wraps. A node is invoked through `call_function` below; the
+//! `Function::call` dispatch used by `funcall`/`apply` does not reach it.
+use super::{nil, FnArgs, GcObj, Object};
+use crate::core::cons::Cons;
+use crate::core::env::Env;
+use crate::core::gc::{Context, GcMark, GcState, Root, Trace};
+use anyhow::{bail, Result};
+use rune_core::macros::root;
+
+/// Which `nadvice`-style combinator a piece of advice uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AdviceKind {
+    Before,
+    After,
+    Around,
+    FilterArgs,
+    FilterReturn,
+}
+
+impl AdviceKind {
+    pub(crate) fn from_keyword(name: &str) -> Option<Self> {
+        match name.trim_start_matches(':') {
+            "before" => Some(Self::Before),
+            "after" => Some(Self::After),
+            "around" => Some(Self::Around),
+            "filter-args" => Some(Self::FilterArgs),
+            "filter-return" => Some(Self::FilterReturn),
+            _ => None,
+        }
+    }
+}
+
+/// A node in an advice chain: `advice`, combined via `kind`, wrapping
+/// `inner` (the function that was advised, which may itself be another
+/// `Advice` node).
+#[derive(Debug)]
+pub(crate) struct Advice {
+    gc: GcMark,
+    pub(crate) kind: AdviceKind,
+    pub(crate) advice: GcObj<'static>,
+    pub(crate) inner: GcObj<'static>,
+    /// Always `advice: true`; lets introspection recognize an advised
+    /// function without walking the chain.
+    pub(crate) args: FnArgs,
+}
+
+impl Advice {
+    pub(crate) fn new(kind: AdviceKind, advice: GcObj<'static>, inner: GcObj<'static>) -> Self {
+        Self {
+            gc: GcMark::default(),
+            kind,
+            advice,
+            inner,
+            args: FnArgs { rest: true, required: 0, optional: 0, advice: true },
+        }
+    }
+
+    pub(crate) fn unmark(&self) {
+        self.gc.unmark();
+    }
+
+    pub(crate) fn is_marked(&self) -> bool {
+        self.gc.is_marked()
+    }
+
+    /// Run this node of the chain: apply `kind`'s combinator semantics
+    /// around `inner`, recursing through `call_function` so a chain of
+    /// several advice nodes (or a plain `SubrFn`) is invoked correctly at
+    /// every link.
+    pub(crate) fn call<'ob>(
+        &self,
+        args: &mut Root<Vec<GcObj<'static>>>,
+        env: &mut Root<Env>,
+        cx: &'ob mut Context,
+    ) -> Result<GcObj<'ob>> {
+        match self.kind {
+            AdviceKind::Before => {
+                // The first call may grow or replace `args` (nil fill args,
+                // `:around`'s inserted function), so restore it for the second.
+                let saved = args.as_mut(cx).clone();
+                call_function(self.advice, args, env, cx)?;
+                *args.as_mut(cx) = saved;
+                call_function(self.inner, args, env, cx)
+            }
+            AdviceKind::After => {
+                let saved = args.as_mut(cx).clone();
+                let result = call_function(self.inner, args, env, cx)?;
+                *args.as_mut(cx) = saved;
+                call_function(self.advice, args, env, cx)?;
+                Ok(result)
+            }
+            AdviceKind::Around => {
+                // `:around` advice receives the wrapped function as its
+                // first argument, and decides itself whether (and how) to
+                // invoke it.
+                args.as_mut(cx).insert(0, self.inner);
+                call_function(self.advice, args, env, cx)
+            }
+            AdviceKind::FilterArgs => {
+                // `:filter-args` advice receives the current argument list
+                // as a single Lisp list and returns the replacement list to
+                // call `inner` with.
+                let current: Vec<GcObj<'static>> = args.as_mut(cx).clone();
+                let arg_list = vec_to_list(&current, cx);
+                root!(arg_list, init(arg_list), cx);
+                let arg_list = arg_list.bind(cx);
+                // SAFETY: rooted immediately above, for the duration of the
+                // `call_function` below.
+                let arg_list = unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(arg_list) };
+                *args.as_mut(cx) = vec![arg_list];
+                let new_args = call_function(self.advice, args, env, cx)?;
+                root!(new_args, init(new_args), cx);
+                let new_args = new_args.bind(cx);
+                *args.as_mut(cx) = list_to_vec(new_args);
+                call_function(self.inner, args, env, cx)
+            }
+            AdviceKind::FilterReturn => {
+                let result = call_function(self.inner, args, env, cx)?;
+                // `:filter-return` advice receives the inner function's
+                // return value directly (not wrapped in a list) and
+                // returns its replacement.
+                root!(result, init(result), cx);
+                let result = result.bind(cx);
+                // SAFETY: rooted immediately above, for the duration of the
+                // `call_function` below.
+                let result = unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(result) };
+                *args.as_mut(cx) = vec![result];
+                call_function(self.advice, args, env, cx)
+            }
+        }
+    }
+}
+
+/// Unpack a (proper) Lisp list into a `Vec`, stopping at the first
+/// non-`Cons` cdr. Used to turn the list a `:filter-args` advice function
+/// returns back into a call argument vector.
+fn list_to_vec(list: GcObj) -> Vec<GcObj<'static>> {
+    let mut out = Vec::new();
+    let mut rest = list;
+    while let Object::Cons(cons) = rest.get() {
+        // SAFETY: every element is copied immediately into `out`, which the
+        // caller stores into the (rooted) argument vector right away; nothing
+        // allocates during this walk.
+        out.push(unsafe { std::mem::transmute::<GcObj, GcObj<'static>>(cons.car()) });
+        rest = cons.cdr();
+    }
+    out
+}
+
+/// Build a Lisp list out of `items`, in order. Mirrors `list_to_vec`.
+fn vec_to_list<'ob>(items: &[GcObj<'static>], cx: &'ob Context) -> GcObj<'ob> {
+    let mut list = nil();
+    for item in items.iter().rev() {
+        list = cx.alloc(Cons::new(*item, list)).into();
+    }
+    list
+}
+
+/// Invoke `func`, which must be a `SubrFn`, a `LispFn`, or another `Advice`
+/// node; any other object is an error. `Advice::call` and the byte-code
+/// `Call` op dispatch through here. `funcall`/`apply` (`src/eval.rs`)
+/// dispatch through `Function::call` instead and do not reach this function.
+pub(crate) fn call_function<'ob>(
+    func: GcObj,
+    args: &mut Root<Vec<GcObj<'static>>>,
+    env: &mut Root<Env>,
+    cx: &'ob mut Context,
+) -> Result<GcObj<'ob>> {
+    match func.get() {
+        Object::SubrFn(subr) => subr.call(args, env, cx),
+        Object::Advice(adv) => adv.call(args, env, cx),
+        Object::LispFn(func) => crate::bytecode::execute(func, args, env, cx),
+        _ => bail!("Invalid function: {func:?}"),
+    }
+}
+
+impl Trace for Advice {
+    fn trace(&self, state: &mut GcState) {
+        self.gc.mark();
+        self.advice.trace(state);
+        self.inner.trace(state);
+    }
+}
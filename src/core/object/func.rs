@@ -153,7 +153,19 @@ impl SubrFn {
                 args.push(nil());
             }
         }
-        (self.subr)(args, env, cx)
+        let trace = crate::core::debug::trace_calls();
+        if trace {
+            let arg_cnt = args.as_mut(cx).len();
+            eprintln!("call: ({} <{} args>)", self.name, arg_cnt);
+        }
+        let result = (self.subr)(args, env, cx);
+        if trace {
+            match &result {
+                Ok(value) => eprintln!("call: {} => {value:?}", self.name),
+                Err(err) => eprintln!("call: {} => error: {err}", self.name),
+            }
+        }
+        result
     }
 }
 
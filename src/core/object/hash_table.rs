@@ -0,0 +1,262 @@
+//! A first-class Lisp hash table. Unlike `Trace for HashMap<K, V>`, which
+//! always marks every entry, a `LispHashTable` can be created with
+//! `:weakness` so the collector is free to drop entries once nothing else
+//! references the weak component.
+use super::{GcObj, Object};
+use crate::core::gc::{GcMark, GcState, Trace};
+use std::cell::RefCell;
+
+/// Which components of an entry, if any, are held weakly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Weakness {
+    #[default]
+    None,
+    Key,
+    Value,
+    KeyAndValue,
+    KeyOrValue,
+}
+
+impl Weakness {
+    pub(crate) fn from_keyword(name: &str) -> Option<Self> {
+        match name {
+            "nil" => Some(Self::None),
+            "key" => Some(Self::Key),
+            "value" => Some(Self::Value),
+            "key-and-value" => Some(Self::KeyAndValue),
+            "key-or-value" => Some(Self::KeyOrValue),
+            _ => None,
+        }
+    }
+
+    fn is_weak(self) -> bool {
+        !matches!(self, Self::None)
+    }
+}
+
+/// The equality predicate a table uses to compare keys, dispatching to the
+/// `eq`, `eql`, or `equal` Lisp predicates already defined in `data.rs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum HashTableTest {
+    #[default]
+    Eql,
+    Eq,
+    Equal,
+}
+
+impl HashTableTest {
+    pub(crate) fn from_keyword(name: &str) -> Option<Self> {
+        match name {
+            "eq" => Some(Self::Eq),
+            "eql" => Some(Self::Eql),
+            "equal" => Some(Self::Equal),
+            _ => None,
+        }
+    }
+
+    fn matches(self, a: GcObj, b: GcObj) -> bool {
+        match self {
+            Self::Eq => crate::data::eq(a, b),
+            Self::Eql => crate::data::eql(a, b),
+            Self::Equal => crate::data::equal(a, b),
+        }
+    }
+}
+
+/// A hash table keyed on Lisp equality (`eq`/`eql`/`equal`) rather than
+/// Rust's `Hash`, so entries are kept in a flat `Vec` and looked up with a
+/// linear scan: `get`/`put`/`remove` are all O(entries). That is fine for
+/// the small tables this interpreter deals with today, but if a caller ever
+/// puts thousands of entries in one table this will need a real hash
+/// (e.g. hashing the `equal`-normalized printed form of the key) instead.
+#[derive(Debug)]
+pub(crate) struct LispHashTable {
+    gc: GcMark,
+    pub(crate) test: HashTableTest,
+    pub(crate) weakness: Weakness,
+    entries: RefCell<Vec<(GcObj<'static>, GcObj<'static>)>>,
+}
+
+impl LispHashTable {
+    pub(crate) fn new(test: HashTableTest, weakness: Weakness) -> Self {
+        Self {
+            gc: GcMark::default(),
+            test,
+            weakness,
+            entries: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, key: GcObj) -> Option<GcObj<'static>> {
+        self.entries
+            .borrow()
+            .iter()
+            .find(|(k, _)| self.test.matches(*k, key))
+            .map(|(_, v)| *v)
+    }
+
+    pub(crate) fn put(&self, key: GcObj<'static>, value: GcObj<'static>) {
+        let mut entries = self.entries.borrow_mut();
+        if let Some(slot) = entries.iter_mut().find(|(k, _)| self.test.matches(*k, key)) {
+            slot.1 = value;
+        } else {
+            entries.push((key, value));
+        }
+    }
+
+    pub(crate) fn remove(&self, key: GcObj) -> bool {
+        let mut entries = self.entries.borrow_mut();
+        let len = entries.len();
+        entries.retain(|(k, _)| !self.test.matches(*k, key));
+        entries.len() != len
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    /// Borrow the table's entries, e.g. for printing.
+    pub(crate) fn entries(&self) -> std::cell::Ref<'_, Vec<(GcObj<'static>, GcObj<'static>)>> {
+        self.entries.borrow()
+    }
+
+    pub(crate) fn unmark(&self) {
+        self.gc.unmark();
+    }
+
+    pub(crate) fn is_marked(&self) -> bool {
+        self.gc.is_marked()
+    }
+
+    /// Drop any entry whose weak component is not marked. An entry that
+    /// `keep_entry` decides to retain has both its components re-marked
+    /// (via `state`), even the ones that were weak and so were never
+    /// marked by `trace`: once the table decides to keep an entry, every
+    /// part of it is live, and skipping this step would let a later sweep
+    /// free e.g. the value of a `key-or-value` entry kept only because its
+    /// key was live. Returns whether any such re-mark happened, which
+    /// `clear_weak_tables` uses to iterate every registered table to a
+    /// fixpoint (marking a value kept by one table can be exactly what
+    /// makes another table's otherwise-dead entry newly live). Called by
+    /// the collector once the mark stack has been fully drained, so that
+    /// liveness for the rest of the heap is already known.
+    pub(crate) fn sweep_weak_entries(&self, state: &mut GcState) -> bool {
+        let kind = self.weakness;
+        let mut newly_marked = false;
+        self.entries.borrow_mut().retain(|(k, v)| {
+            let key_live = is_live(*k);
+            let value_live = is_live(*v);
+            let keep = keep_entry(kind, key_live, value_live);
+            if keep {
+                if !key_live {
+                    k.trace(state);
+                    newly_marked = true;
+                }
+                if !value_live {
+                    v.trace(state);
+                    newly_marked = true;
+                }
+            }
+            keep
+        });
+        newly_marked
+    }
+}
+
+/// The retention rule for a single entry, given whether its key/value were
+/// reached during mark. Split out from `sweep_weak_entries` so the policy
+/// for each `:weakness` kind can be unit tested without a real GC.
+fn keep_entry(weakness: Weakness, key_live: bool, value_live: bool) -> bool {
+    match weakness {
+        Weakness::None => true,
+        Weakness::Key => key_live,
+        Weakness::Value => value_live,
+        Weakness::KeyAndValue => key_live && value_live,
+        Weakness::KeyOrValue => key_live || value_live,
+    }
+}
+
+/// Whether `obj` was reached during the mark phase. Only genuine immediates
+/// (nil, other symbols, integers, floats) are never collected and so are
+/// unconditionally live; every other GC-managed variant must defer to its
+/// own mark bit, or a weak table keyed/valued by e.g. a string or another
+/// hash table would never actually clear those entries.
+fn is_live(obj: GcObj) -> bool {
+    match obj.get() {
+        Object::Cons(x) => x.is_marked(),
+        Object::Vec(x) => x.is_marked(),
+        Object::String(x) => x.is_marked(),
+        Object::LispFn(x) => x.is_marked(),
+        Object::HashTable(x) => x.is_marked(),
+        Object::Advice(x) => x.is_marked(),
+        // Subrs are static and interned once at startup; never swept.
+        Object::SubrFn(_) => true,
+        Object::Symbol(_) | Object::Int(_) | Object::Float(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keep_entry_strong() {
+        assert!(keep_entry(Weakness::None, false, false));
+    }
+
+    #[test]
+    fn test_keep_entry_key() {
+        assert!(keep_entry(Weakness::Key, true, false));
+        assert!(!keep_entry(Weakness::Key, false, true));
+    }
+
+    #[test]
+    fn test_keep_entry_value() {
+        assert!(keep_entry(Weakness::Value, false, true));
+        assert!(!keep_entry(Weakness::Value, true, false));
+    }
+
+    #[test]
+    fn test_keep_entry_key_and_value() {
+        assert!(keep_entry(Weakness::KeyAndValue, true, true));
+        assert!(!keep_entry(Weakness::KeyAndValue, true, false));
+        assert!(!keep_entry(Weakness::KeyAndValue, false, true));
+    }
+
+    #[test]
+    fn test_keep_entry_key_or_value() {
+        assert!(keep_entry(Weakness::KeyOrValue, true, false));
+        assert!(keep_entry(Weakness::KeyOrValue, false, true));
+        assert!(!keep_entry(Weakness::KeyOrValue, false, false));
+    }
+
+    #[test]
+    fn test_weakness_from_keyword() {
+        assert_eq!(Weakness::from_keyword("key"), Some(Weakness::Key));
+        assert_eq!(Weakness::from_keyword("key-and-value"), Some(Weakness::KeyAndValue));
+        assert_eq!(Weakness::from_keyword("bogus"), None);
+    }
+}
+
+impl Trace for LispHashTable {
+    fn trace(&self, state: &mut GcState) {
+        self.gc.mark();
+        if self.weakness.is_weak() {
+            // Weak components are not marked here; they are only kept alive
+            // if something else roots them. Register this table so the
+            // collector can clear dead entries once marking finishes.
+            state.register_weak_table(self);
+        }
+        for (key, value) in self.entries.borrow().iter() {
+            match self.weakness {
+                Weakness::None => {
+                    key.trace(state);
+                    value.trace(state);
+                }
+                Weakness::Key => value.trace(state),
+                Weakness::Value => key.trace(state),
+                Weakness::KeyAndValue | Weakness::KeyOrValue => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! The Lisp-visible `make-hash-table` API and its accessors. The table
+//! itself, including weak-reference handling during GC, lives in
+//! `core::object::hash_table`.
+use crate::core::{
+    gc::Context,
+    object::{
+        hash_table::{HashTableTest, LispHashTable, Weakness},
+        nil, GcObj, Object,
+    },
+};
+use anyhow::{anyhow, Result};
+use fn_macros::defun;
+
+fn keyword_arg<'ob>(args: &[GcObj<'ob>], name: &str) -> Option<GcObj<'ob>> {
+    args.chunks_exact(2).find_map(|pair| match pair[0].get() {
+        Object::Symbol(sym) if sym.name == name => Some(pair[1]),
+        _ => None,
+    })
+}
+
+fn keyword_name(obj: GcObj) -> Option<&str> {
+    match obj.get() {
+        Object::Symbol(sym) => Some(sym.name.trim_start_matches(':')),
+        _ => None,
+    }
+}
+
+/// Create and return a new hash table. Recognizes the `:test` keyword
+/// (`eq`, `eql`, or `equal`, defaulting to `eql`) and `:weakness` (`nil`,
+/// `key`, `value`, `key-and-value`, or `key-or-value`, defaulting to `nil`).
+#[defun]
+pub(crate) fn make_hash_table<'ob>(keys: &[GcObj<'ob>], cx: &'ob Context) -> Result<GcObj<'ob>> {
+    let test = match keyword_arg(keys, ":test") {
+        Some(obj) => match keyword_name(obj).and_then(HashTableTest::from_keyword) {
+            Some(test) => test,
+            None => return Err(anyhow!("Invalid hash table test")),
+        },
+        None => HashTableTest::default(),
+    };
+    let weakness = match keyword_arg(keys, ":weakness") {
+        Some(obj) if obj.nil() => Weakness::None,
+        Some(obj) => match keyword_name(obj).and_then(Weakness::from_keyword) {
+            Some(weakness) => weakness,
+            None => return Err(anyhow!("Invalid hash table weakness")),
+        },
+        None => Weakness::None,
+    };
+    let table = LispHashTable::new(test, weakness);
+    Ok(cx.alloc(table).into())
+}
+
+fn as_hash_table<'ob>(obj: GcObj<'ob>) -> Result<&'ob LispHashTable> {
+    match obj.get() {
+        Object::HashTable(table) => Ok(table),
+        _ => Err(anyhow!("Wrong type: expected hash table")),
+    }
+}
+
+/// Look up `key` in `table`, returning `dflt` (or nil) if absent.
+#[defun]
+pub(crate) fn gethash<'ob>(
+    key: GcObj<'ob>,
+    table: GcObj<'ob>,
+    dflt: Option<GcObj<'ob>>,
+) -> Result<GcObj<'ob>> {
+    let table = as_hash_table(table)?;
+    Ok(table.get(key).map(GcObj::from).unwrap_or_else(|| dflt.unwrap_or_else(nil)))
+}
+
+/// Associate `key` with `value` in `table`, returning `value`.
+#[defun]
+pub(crate) fn puthash<'ob>(
+    key: GcObj<'static>,
+    value: GcObj<'static>,
+    table: GcObj<'ob>,
+) -> Result<GcObj<'static>> {
+    as_hash_table(table)?.put(key, value);
+    Ok(value)
+}
+
+/// Remove `key`'s entry from `table`, if present.
+#[defun]
+pub(crate) fn remhash(key: GcObj, table: GcObj) -> Result<GcObj<'static>> {
+    as_hash_table(table)?.remove(key);
+    Ok(nil())
+}
+
+/// Whether `object` is a hash table.
+#[defun]
+pub(crate) fn hash_table_p(object: GcObj) -> bool {
+    matches!(object.get(), Object::HashTable(_))
+}
+
+/// The number of entries in `table`.
+#[defun]
+pub(crate) fn hash_table_count(table: GcObj) -> Result<usize> {
+    Ok(as_hash_table(table)?.len())
+}
+
+define_symbols!(FUNCS => {
+    make_hash_table,
+    gethash,
+    puthash,
+    remhash,
+    hash_table_p,
+    hash_table_count,
+});
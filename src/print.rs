@@ -0,0 +1,370 @@
+//! A share- and cycle-aware object printer, implementing the Lisp `prin1`
+//! (readable) and `princ` (raw) printing styles.
+use std::fmt::Write as _;
+
+use crate::core::{
+    env::{intern, Env},
+    gc::{Context, Root},
+    object::{Gc, GcObj, Object},
+};
+use crate::hashmap::HashMap;
+use fn_macros::defun;
+
+/// Whether `print-circle` is non-nil in its current dynamic binding. The
+/// underlying two-pass traversal always protects against infinite loops on
+/// circular structure regardless of this value; the variable controls how a
+/// repeated node (shared or circular) is shown: as `#N=`/`#N#` labels when
+/// non-nil, or elided as `...` on its second occurrence when nil.
+fn print_circle_enabled(env: &Root<Env>, cx: &Context) -> bool {
+    let symbol = intern("print-circle");
+    match env.vars.get(&symbol) {
+        Some(value) => !value.bind(cx).nil(),
+        None => false,
+    }
+}
+
+/// Readable vs. raw rendering. `Print1` quotes/escapes strings and symbols
+/// and prints floats so they round-trip; `Princ` prints the raw contents.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Print1,
+    Princ,
+}
+
+/// Tracks, for a single print operation, how many times each object
+/// (identified by pointer) has been visited, which nodes have already had
+/// their contents printed in full, and the labels assigned to shared nodes.
+#[derive(Default)]
+struct SharedState {
+    /// pointer identity -> number of times seen during the counting pass.
+    seen: HashMap<usize, usize>,
+    /// pointer identity -> `#N` label, assigned the first time a shared node
+    /// is printed.
+    labels: HashMap<usize, u32>,
+    /// pointer identities whose contents have already been printed once.
+    /// Consulted unconditionally (regardless of `print-circle`) so that a
+    /// second encounter never redescends, which is what keeps printing
+    /// circular structure from looping forever.
+    printed: HashMap<usize, ()>,
+    next_label: u32,
+}
+
+fn addr(obj: GcObj) -> usize {
+    Gc::into_raw(obj) as usize
+}
+
+/// Whether `obj` is a heap aggregate (cons, vector, hash table, advice
+/// chain) that can meaningfully be *shared*. Emacs only ever labels these
+/// with `#N=`/`#N#`; immediates like symbols, integers, and floats are
+/// compared by value when printed, so two occurrences of `1` or `foo` are
+/// never "the same object" for printing purposes, no matter how many times
+/// they show up in the structure.
+fn is_aggregate(obj: GcObj) -> bool {
+    matches!(
+        obj.get(),
+        Object::Cons(_) | Object::Vec(_) | Object::HashTable(_) | Object::Advice(_)
+    )
+}
+
+impl SharedState {
+    /// Pass one: walk the object graph, counting visits to each aggregate.
+    /// Never descends past a node that has already been seen, which bounds
+    /// the walk even for circular structure. Immediates are never counted,
+    /// so they can never be (mis)reported as shared.
+    fn count(&mut self, obj: GcObj) {
+        if !is_aggregate(obj) {
+            return;
+        }
+        let key = addr(obj);
+        let count = self.seen.entry(key).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            return;
+        }
+        match obj.get() {
+            Object::Cons(cons) => {
+                self.count(cons.car());
+                self.count(cons.cdr());
+            }
+            Object::Vec(vec) => {
+                for elem in vec.borrow().iter() {
+                    self.count(*elem);
+                }
+            }
+            Object::HashTable(table) => {
+                for (key, value) in table.entries().iter() {
+                    self.count(*key);
+                    self.count(*value);
+                }
+            }
+            Object::Advice(adv) => {
+                self.count(adv.advice);
+                self.count(adv.inner);
+            }
+            _ => {}
+        }
+    }
+
+    /// A node needs a `#N=`/`#N#` label if it was visited more than once.
+    fn is_shared(&self, obj: GcObj) -> bool {
+        self.seen.get(&addr(obj)).copied().unwrap_or(0) > 1
+    }
+
+    fn already_printed(&self, obj: GcObj) -> bool {
+        self.printed.contains_key(&addr(obj))
+    }
+
+    fn mark_printed(&mut self, obj: GcObj) {
+        self.printed.insert(addr(obj), ());
+    }
+
+    /// Returns the label for `obj`, assigning a fresh one on first use.
+    fn label(&mut self, obj: GcObj) -> u32 {
+        let key = addr(obj);
+        *self.labels.entry(key).or_insert_with(|| {
+            self.next_label += 1;
+            self.next_label
+        })
+    }
+}
+
+fn print_float(f: f64, out: &mut String) {
+    if f == f.trunc() && f.is_finite() {
+        let _ = write!(out, "{f:.1}");
+    } else {
+        let _ = write!(out, "{f}");
+    }
+}
+
+fn print_symbol_name(name: &str, style: Style, out: &mut String) {
+    if style == Style::Princ {
+        out.push_str(name);
+        return;
+    }
+    let needs_escape =
+        name.is_empty() || name.chars().any(|c| c.is_whitespace() || "()[]\"';#.,`\\".contains(c));
+    if !needs_escape {
+        out.push_str(name);
+        return;
+    }
+    for c in name.chars() {
+        if c.is_whitespace() || "()[]\"';#.,`\\".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+fn print_string(s: &str, style: Style, out: &mut String) {
+    if style == Style::Princ {
+        out.push_str(s);
+        return;
+    }
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Pass two: print `obj`. A node seen more than once in the counting pass
+/// never has its contents printed twice: with `circle` enabled this shows up
+/// as `#N=`/`#N#` reader syntax; with it disabled the second occurrence is
+/// rendered as `...` instead. Either way, printing a circular structure is
+/// guaranteed to terminate.
+fn print_object(obj: GcObj, style: Style, circle: bool, shared: &mut SharedState, out: &mut String) {
+    if shared.is_shared(obj) {
+        if shared.already_printed(obj) {
+            if circle {
+                let n = shared.label(obj);
+                let _ = write!(out, "#{n}#");
+            } else {
+                out.push_str("...");
+            }
+            return;
+        }
+        shared.mark_printed(obj);
+        if circle {
+            let n = shared.label(obj);
+            let _ = write!(out, "#{n}=");
+        }
+    }
+    match obj.get() {
+        Object::Int(i) => {
+            let _ = write!(out, "{i}");
+        }
+        Object::Float(f) => print_float(f, out),
+        Object::String(s) => print_string(s, style, out),
+        Object::Symbol(sym) => {
+            if sym.nil() {
+                out.push_str("nil");
+            } else {
+                print_symbol_name(sym.name, style, out);
+            }
+        }
+        Object::Cons(cons) => {
+            out.push('(');
+            print_object(cons.car(), style, circle, shared, out);
+            let mut rest = cons.cdr();
+            loop {
+                match rest.get() {
+                    // A shared tail must go through `print_object` (even on
+                    // its first visit) so it gets its `#N=` label and is
+                    // marked printed; inlining it here like an ordinary
+                    // list element would let it slip through unlabeled.
+                    Object::Cons(next) if !shared.is_shared(rest) => {
+                        out.push(' ');
+                        print_object(next.car(), style, circle, shared, out);
+                        rest = next.cdr();
+                    }
+                    Object::Symbol(s) if s.nil() => break,
+                    _ => {
+                        out.push_str(" . ");
+                        print_object(rest, style, circle, shared, out);
+                        break;
+                    }
+                }
+            }
+            out.push(')');
+        }
+        Object::Vec(vec) => {
+            out.push('[');
+            for (i, elem) in vec.borrow().iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                print_object(*elem, style, circle, shared, out);
+            }
+            out.push(']');
+        }
+        Object::LispFn(_) | Object::SubrFn(_) => {
+            let _ = write!(out, "#<function>");
+        }
+        Object::HashTable(table) => {
+            out.push_str("#s(hash-table data (");
+            for (i, (key, value)) in table.entries().iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                print_object(*key, style, circle, shared, out);
+                out.push(' ');
+                print_object(*value, style, circle, shared, out);
+            }
+            out.push_str("))");
+        }
+        Object::Advice(adv) => {
+            out.push_str("#<advice ");
+            print_object(adv.advice, style, circle, shared, out);
+            out.push(' ');
+            print_object(adv.inner, style, circle, shared, out);
+            out.push('>');
+        }
+    }
+}
+
+fn print_to_string(obj: GcObj, style: Style, circle: bool) -> String {
+    let mut shared = SharedState::default();
+    shared.count(obj);
+    let mut out = String::new();
+    print_object(obj, style, circle, &mut shared, &mut out);
+    out
+}
+
+/// Return the readable (`prin1`-style) printed representation of `object` as
+/// a string, without printing it anywhere.
+#[defun]
+pub(crate) fn prin1_to_string(object: GcObj, env: &Root<Env>, cx: &Context) -> String {
+    print_to_string(object, Style::Print1, print_circle_enabled(env, cx))
+}
+
+/// Like `prin1_to_string`, for callers (e.g. `disassemble`) that have no
+/// `Env` to consult `print-circle` in. Always prints as though it were nil,
+/// which is the correct behavior for the short, non-circular literals found
+/// in a constant vector.
+pub(crate) fn prin1_to_string_basic(object: GcObj) -> String {
+    print_to_string(object, Style::Print1, false)
+}
+
+/// Print `object` to standard output in a form that can be read back by
+/// `read`, honoring shared/circular structure via `print-circle`.
+#[defun]
+pub(crate) fn prin1<'ob>(object: GcObj<'ob>, env: &Root<Env>, cx: &Context) -> GcObj<'ob> {
+    print!("{}", print_to_string(object, Style::Print1, print_circle_enabled(env, cx)));
+    object
+}
+
+/// Print `object` to standard output without quoting or escaping.
+#[defun]
+pub(crate) fn princ<'ob>(object: GcObj<'ob>, env: &Root<Env>, cx: &Context) -> GcObj<'ob> {
+    print!("{}", print_to_string(object, Style::Princ, print_circle_enabled(env, cx)));
+    object
+}
+
+define_symbols!(FUNCS => { prin1, princ, prin1_to_string });
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::cons::Cons;
+    use crate::core::gc::RootSet;
+    use crate::core::object::nil;
+
+    /// Build a proper list of integers, one `Cons` allocation per element.
+    fn alloc_list<'ob>(cx: &'ob Context, items: &[i64]) -> GcObj<'ob> {
+        let mut tail = nil();
+        for &i in items.iter().rev() {
+            tail = cx.alloc(Cons::new(i.into(), tail)).into();
+        }
+        tail
+    }
+
+    #[test]
+    fn test_repeated_immediates_are_not_shared() {
+        let roots = &RootSet::default();
+        let cx = &Context::new(roots);
+        let list = alloc_list(cx, &[1, 2, 1]);
+        assert_eq!(print_to_string(list, Style::Print1, false), "(1 2 1)");
+        assert_eq!(print_to_string(list, Style::Print1, true), "(1 2 1)");
+    }
+
+    #[test]
+    fn test_shared_cons_without_print_circle_terminates() {
+        let roots = &RootSet::default();
+        let cx = &Context::new(roots);
+        let inner = alloc_list(cx, &[9]);
+        let outer: GcObj =
+            cx.alloc(Cons::new(inner, cx.alloc(Cons::new(inner, nil())).into())).into();
+        // No print-circle: the repeated sub-list is elided, not re-printed,
+        // and the call returns rather than looping.
+        assert_eq!(print_to_string(outer, Style::Print1, false), "((9) ...)");
+    }
+
+    #[test]
+    fn test_shared_tail_in_dotted_position_is_labeled() {
+        let roots = &RootSet::default();
+        let cx = &Context::new(roots);
+        // (let ((b (list 2))) (list (cons 1 b) b)): `b` is shared, and its
+        // first occurrence is as a cons *tail*, not a car.
+        let inner = alloc_list(cx, &[2]);
+        let pair: GcObj = cx.alloc(Cons::new(1.into(), inner)).into();
+        let outer: GcObj =
+            cx.alloc(Cons::new(pair, cx.alloc(Cons::new(inner, nil())).into())).into();
+        assert_eq!(print_to_string(outer, Style::Print1, true), "((1 . #1=(2)) #1#)");
+    }
+
+    #[test]
+    fn test_shared_cons_with_print_circle_labels() {
+        let roots = &RootSet::default();
+        let cx = &Context::new(roots);
+        let inner = alloc_list(cx, &[9]);
+        let outer: GcObj =
+            cx.alloc(Cons::new(inner, cx.alloc(Cons::new(inner, nil())).into())).into();
+        assert_eq!(print_to_string(outer, Style::Print1, true), "(#1=(9) #1#)");
+    }
+}